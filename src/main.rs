@@ -4,10 +4,18 @@ use nu_protocol::{
     CallInfo, Primitive, ReturnSuccess, ReturnValue, Signature, SyntaxShape, TaggedDictBuilder,
     UntaggedValue, Value,
 };
+use nu_source::Tag;
+use regex::Regex;
 use std::fmt;
+use std::path::Path;
 
 const LOG_KEYWORD: &str = "UnityEngine.Debug:Log";
 const EMPTY_NEWLINE: &str = "\n\n";
+// `(at <file>:<line>)` suffix Unity appends to frames mapping back to source.
+const FRAME_REGEX: &str = r"\(at (.+):(\d+)\)\s*$";
+// How many consecutive keyword-less blocks we tolerate before committing to
+// Player.log mode and streaming the rest instead of buffering to classify.
+const PLAYER_LOG_THRESHOLD: usize = 64;
 
 #[derive(Debug, PartialEq)]
 pub enum LogType {
@@ -23,14 +31,48 @@ impl fmt::Display for LogType {
     }
 }
 
-pub struct LogLine<'a> {
+impl LogType {
+    /// Ordered severity used for `--min-level` comparisons. `Unknown` is exempt
+    /// from the threshold in `keep` so player logs are never dropped by it.
+    fn severity(&self) -> u8 {
+        match self {
+            LogType::Unknown => 0,
+            LogType::Log => 1,
+            LogType::Warning => 2,
+            LogType::Error => 3,
+        }
+    }
+
+    /// ANSI foreground escape keyed on severity, mirroring the color scheme
+    /// log listeners use: bright red for errors, yellow for warnings, the
+    /// terminal default for info and dim for unrecognized player-log lines.
+    fn ansi(&self) -> &'static str {
+        match self {
+            LogType::Error => "\x1b[91m",
+            LogType::Warning => "\x1b[33m",
+            LogType::Log => "\x1b[39m",
+            LogType::Unknown => "\x1b[2m",
+        }
+    }
+
+    /// Parse a `--min-level` flag value (`log`, `warning`, `error`).
+    fn from_level(name: &str) -> Option<LogType> {
+        match name.to_lowercase().as_str() {
+            "log" => Some(LogType::Log),
+            "warning" => Some(LogType::Warning),
+            "error" => Some(LogType::Error),
+            _ => None,
+        }
+    }
+}
+
+pub struct LogLine {
     pub log_type: LogType,
-    pub message: &'a str,
-    pub callstack: &'a str,
-    pub trimmed_callstack: &'a str,
+    pub message: String,
+    pub trimmed_callstack: String,
 }
 
-impl LogLine<'_> {
+impl LogLine {
     fn same(&self, other: &LogLine) -> bool {
         self.log_type == other.log_type && self.message == other.message
     }
@@ -39,6 +81,28 @@ impl LogLine<'_> {
 struct UnityLog {
     count: usize,
     no_collapse: bool,
+    min_level: Option<LogType>,
+    match_re: Option<Regex>,
+    ignore_re: Option<Regex>,
+    frames: bool,
+    color: bool,
+    snippet: bool,
+    project_root: Option<String>,
+    // Streaming state: a tail buffer carrying an incomplete block across
+    // chunks, the lines held back for the collapse pass, and the tag of the
+    // most recent input used when building rows in `end_filter`.
+    tail: String,
+    lines: Vec<LogLine>,
+    tag: Option<Tag>,
+    frame_re: Regex,
+    // Classification state. `editor_mode` becomes true the moment a block
+    // carrying `LOG_KEYWORD` is seen; `player_mode` once `PLAYER_LOG_THRESHOLD`
+    // keyword-less blocks pass without one. Until either settles, keyword-less
+    // blocks are held in `pending` because we cannot yet tell an Editor.log
+    // header from a Player.log. See `consume` for the either/or resolution.
+    editor_mode: bool,
+    player_mode: bool,
+    pending: Vec<LogLine>,
 }
 
 impl Default for UnityLog {
@@ -46,6 +110,20 @@ impl Default for UnityLog {
         UnityLog {
             count: 3,
             no_collapse: false,
+            min_level: None,
+            match_re: None,
+            ignore_re: None,
+            frames: false,
+            color: false,
+            snippet: false,
+            project_root: None,
+            tail: String::new(),
+            lines: Vec::new(),
+            tag: None,
+            frame_re: Regex::new(FRAME_REGEX).expect("valid frame regex"),
+            editor_mode: false,
+            player_mode: false,
+            pending: Vec::new(),
         }
     }
 }
@@ -57,121 +135,263 @@ impl UnityLog {
         }
     }
 
-    fn len(&mut self, value: Value) -> Result<Vec<Value>, ShellError> {
-        match &value.value {
-            UntaggedValue::Primitive(Primitive::String(s)) => {
-                let tag = &value.tag;
-
-                let sanitized = s.replace("\r\n", "\n");
-                let input = sanitized.replace("\r", "\n");
-
-                let mut lines: Vec<LogLine> = input
-                    .split_terminator(EMPTY_NEWLINE)
-                    .filter(|s| s.contains(LOG_KEYWORD))
-                    .map(|block| -> Option<LogLine> {
-                        let index = block.rfind(LOG_KEYWORD)?;
-                        let (_, bottom) = block.split_at(index);
-                        let (_, user_log) = bottom.split_once('\n')?;
-                        // remove our custom logging methods
-                        let custom_method = user_log.lines().next().unwrap_or("");
-                        let trimmed = match custom_method.contains("Debug")
-                            || custom_method.contains("Log")
-                        {
-                            true => user_log
-                                .split_once('\n')
-                                .map_or_else(|| user_log, |(_a, b)| b),
-                            false => user_log,
-                        };
-
-                        let type_line = bottom.trim_start_matches(LOG_KEYWORD);
-                        let log_type: LogType;
-                        if type_line.starts_with("Error") {
-                            log_type = LogType::Error;
-                        } else if type_line.starts_with("Warning") {
-                            log_type = LogType::Warning;
-                        } else {
-                            log_type = LogType::Log;
-                        }
-
-                        // next works like First() here *eyeroll*
-                        Some(LogLine {
-                            log_type,
-                            message: block.lines().next().unwrap_or(""),
-                            callstack: block,
-                            trimmed_callstack: trimmed,
-                        })
-                    })
-                    .flatten() // removes None elements
-                    .collect();
-
-                //TODO: check here if we have any lines and if not then we have a player log
-                // that we need to check differently
-                if lines.is_empty() {
-                    lines = input
-                        .split_terminator(EMPTY_NEWLINE)
-                        .map(|block| -> Option<LogLine> {
-                            Some(LogLine {
-                                log_type: LogType::Unknown,
-                                message: block.lines().next()?,
-                                callstack: block,
-                                trimmed_callstack: block.split_once('\n')?.1,
-                            })
-                        })
-                        .flatten()
-                        .collect()
-                }
+    /// Parse a single `\n\n`-delimited block into an owned [`LogLine`].
+    ///
+    /// Editor blocks carry the `UnityEngine.Debug:Log` marker and are split
+    /// into type/message/trimmed callstack; Player.log blocks have no marker and are
+    /// surfaced as `Unknown` so large player logs still stream block by block.
+    fn parse_block(&self, block: &str) -> Option<LogLine> {
+        if block.trim().is_empty() {
+            return None;
+        }
 
-                if self.no_collapse {
-                    lines.sort_by_key(|x| x.message);
-                    lines.dedup_by(|a, b| a.same(b));
-                }
+        if block.contains(LOG_KEYWORD) {
+            let index = block.rfind(LOG_KEYWORD)?;
+            let (_, bottom) = block.split_at(index);
+            let (_, user_log) = bottom.split_once('\n')?;
+            // remove our custom logging methods
+            let custom_method = user_log.lines().next().unwrap_or("");
+            let trimmed = match custom_method.contains("Debug") || custom_method.contains("Log") {
+                true => user_log
+                    .split_once('\n')
+                    .map_or_else(|| user_log, |(_a, b)| b),
+                false => user_log,
+            };
+
+            let type_line = bottom.trim_start_matches(LOG_KEYWORD);
+            let log_type = if type_line.starts_with("Error") {
+                LogType::Error
+            } else if type_line.starts_with("Warning") {
+                LogType::Warning
+            } else {
+                LogType::Log
+            };
+
+            // next works like First() here *eyeroll*
+            Some(LogLine {
+                log_type,
+                message: block.lines().next().unwrap_or("").to_string(),
+                trimmed_callstack: trimmed.to_string(),
+            })
+        } else {
+            Some(LogLine {
+                log_type: LogType::Unknown,
+                message: block.lines().next()?.to_string(),
+                trimmed_callstack: block.split_once('\n').map_or("", |(_a, b)| b).to_string(),
+            })
+        }
+    }
+
+    /// Apply the per-line `--min-level`/`--match`/`--ignore` filters.
+    fn keep(&self, line: &LogLine) -> bool {
+        if let Some(threshold) = &self.min_level {
+            // `Unknown` (player-log lines) carry no real severity, so exempt
+            // them from the threshold rather than dropping them wholesale.
+            if line.log_type != LogType::Unknown
+                && line.log_type.severity() < threshold.severity()
+            {
+                return false;
+            }
+        }
+
+        if let Some(re) = &self.match_re {
+            if !re.is_match(&line.message) {
+                return false;
+            }
+        }
 
-                let rows = lines
-                    .into_iter()
-                    .map(|line| {
-                        let mut dict = TaggedDictBuilder::new(tag);
-
-                        dict.insert_untagged(
-                            "type",
-                            UntaggedValue::string(line.log_type.to_string()).into_value(tag),
-                        );
-
-                        dict.insert_untagged(
-                            "message",
-                            UntaggedValue::string(line.message).into_value(tag),
-                        );
-
-                        let truncated: String = line
-                            .trimmed_callstack
-                            .lines()
-                            .take(self.count)
-                            .map(|x| x.trim().to_string())
-                            .collect();
-
-                        dict.insert_untagged(
-                            "short",
-                            UntaggedValue::string(truncated).into_value(tag),
-                        );
-
-                        //TODO: add the full stacktrace as a table with colums: method, parameters, line
-
-                        if dict.is_empty() {
-                            Value::nothing()
-                        } else {
-                            dict.into_value()
-                        }
-                    })
-                    .collect();
-
-                Ok(rows)
+        if let Some(re) = &self.ignore_re {
+            if re.is_match(&line.message) {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// Classify a parsed block: a log is treated as an Editor.log (keyword
+    /// blocks kept, keyword-less spew dropped) *or* a Player.log (keyword-less
+    /// blocks surfaced as `Unknown`). The mode is settled by the leading blocks;
+    /// a keyword block that arrives after Player mode is committed is emitted
+    /// with its parsed type alongside the `Unknown` blocks.
+    ///
+    /// Keyword-less blocks are parked in `pending` only until the mode settles:
+    /// the first keyword block picks Editor mode and discards what was parked,
+    /// while `PLAYER_LOG_THRESHOLD` keyword-less blocks in a row pick Player
+    /// mode, flush the parked blocks, and stream every block thereafter — so a
+    /// giant Player.log runs in bounded memory instead of buffering whole.
+    fn consume(&mut self, line: LogLine, out: &mut Vec<ReturnValue>) {
+        if line.log_type == LogType::Unknown {
+            if self.editor_mode {
+                // Editor.log — drop asset-import/init spew as the baseline did.
+                return;
+            }
+            if self.player_mode {
+                self.route(line, out);
+                return;
+            }
+            self.pending.push(line);
+            if self.pending.len() >= PLAYER_LOG_THRESHOLD {
+                self.player_mode = true;
+                for parked in std::mem::take(&mut self.pending) {
+                    self.route(parked, out);
+                }
             }
-            _ => Err(ShellError::labeled_error(
-                "Unrecognized type in stream",
-                "'len' given non-string info by this",
-                value.tag.span,
-            )),
+            return;
+        }
+
+        // A keyword block: settle Editor mode unless we already committed to
+        // Player mode (in which case it just streams like any other block).
+        if !self.editor_mode && !self.player_mode {
+            self.editor_mode = true;
+            self.pending.clear();
+        }
+        self.route(line, out);
+    }
+
+    /// Route a kept line to output: stream it immediately with `count = 1` under
+    /// `--no-collapse`, otherwise hold it for the `end_filter` collapse pass.
+    fn route(&mut self, line: LogLine, out: &mut Vec<ReturnValue>) {
+        if !self.keep(&line) {
+            return;
+        }
+
+        if self.no_collapse {
+            let tag = self.tag.clone().unwrap_or_else(Tag::unknown);
+            out.push(ReturnSuccess::value(self.build_row(&line, &tag, 1)));
+        } else {
+            self.lines.push(line);
+        }
+    }
+
+    /// Read the source line referenced by the topmost frame of an error/warning
+    /// that carries an `(at file:line)` reference, with one line of context
+    /// above and below. Returns `None` (rendered as an empty `source` cell)
+    /// whenever no frame carries a reference, the file can't be read, or the
+    /// line number falls outside the file.
+    fn snippet_for(&self, line: &LogLine) -> Option<String> {
+        if !matches!(line.log_type, LogType::Error | LogType::Warning) {
+            return None;
+        }
+
+        let root = self.project_root.as_ref()?;
+        let caps = line
+            .trimmed_callstack
+            .lines()
+            .find_map(|frame| self.frame_re.captures(frame.trim()))?;
+        let file = &caps[1];
+        let line_no: usize = caps[2].parse().ok()?;
+
+        // Frame references are expected to be project-relative; an absolute path
+        // would make `join` discard `root`, so treat it as a mismatch.
+        if Path::new(file).is_absolute() {
+            return None;
+        }
+
+        let content = std::fs::read_to_string(Path::new(root).join(file)).ok()?;
+        let source: Vec<&str> = content.lines().collect();
+        if line_no == 0 || line_no > source.len() {
+            return None;
+        }
+
+        let index = line_no - 1;
+        let start = index.saturating_sub(1);
+        let end = (index + 1).min(source.len() - 1);
+        Some(source[start..=end].join("\n"))
+    }
+
+    /// Wrap `text` in the severity color with a trailing reset, or return it
+    /// untouched when `--color` is absent so piped output stays clean.
+    fn colorize(&self, text: &str, log_type: &LogType) -> String {
+        if self.color {
+            format!("{}{}\x1b[0m", log_type.ansi(), text)
+        } else {
+            text.to_string()
         }
     }
+
+    /// Build a single output row for a parsed log line. `count` is the number
+    /// of identical entries that collapsed into this row.
+    fn build_row(&self, line: &LogLine, tag: &Tag, count: usize) -> Value {
+        let mut dict = TaggedDictBuilder::new(tag);
+
+        dict.insert_untagged(
+            "type",
+            UntaggedValue::string(self.colorize(&line.log_type.to_string(), &line.log_type))
+                .into_value(tag),
+        );
+
+        dict.insert_untagged(
+            "message",
+            UntaggedValue::string(self.colorize(&line.message, &line.log_type)).into_value(tag),
+        );
+
+        dict.insert_untagged("count", UntaggedValue::int(count as i64).into_value(tag));
+
+        let truncated: String = line
+            .trimmed_callstack
+            .lines()
+            .take(self.count)
+            .map(|x| x.trim().to_string())
+            .collect();
+
+        dict.insert_untagged("short", UntaggedValue::string(truncated).into_value(tag));
+
+        if self.frames {
+            let frames: Vec<Value> = line
+                .trimmed_callstack
+                .lines()
+                .map(|frame| frame.trim())
+                .filter(|frame| !frame.is_empty())
+                .map(|frame| {
+                    let mut frame_dict = TaggedDictBuilder::new(tag);
+
+                    // Pull off the `(at file:line)` tail if present.
+                    let (rest, file, line_no) = match self.frame_re.captures(frame) {
+                        Some(caps) => (
+                            self.frame_re.replace(frame, "").trim().to_string(),
+                            caps[1].to_string(),
+                            caps[2].to_string(),
+                        ),
+                        None => (frame.to_string(), String::new(), String::new()),
+                    };
+
+                    // Split `Class:Method (args)` into method and parameters.
+                    let (method, parameters) = match rest.split_once('(') {
+                        Some((m, args)) => (
+                            m.trim().to_string(),
+                            args.trim_end_matches(')').trim().to_string(),
+                        ),
+                        None => (rest.trim().to_string(), String::new()),
+                    };
+
+                    frame_dict.insert_untagged(
+                        "method",
+                        UntaggedValue::string(method).into_value(tag),
+                    );
+                    frame_dict.insert_untagged(
+                        "parameters",
+                        UntaggedValue::string(parameters).into_value(tag),
+                    );
+                    frame_dict
+                        .insert_untagged("file", UntaggedValue::string(file).into_value(tag));
+                    frame_dict
+                        .insert_untagged("line", UntaggedValue::string(line_no).into_value(tag));
+
+                    frame_dict.into_value()
+                })
+                .collect();
+
+            dict.insert_untagged("frames", UntaggedValue::table(&frames));
+        }
+
+        if self.snippet {
+            let source = self.snippet_for(line).unwrap_or_default();
+            dict.insert_untagged("source", UntaggedValue::string(source).into_value(tag));
+        }
+
+        dict.into_value()
+    }
 }
 
 impl Plugin for UnityLog {
@@ -186,9 +406,48 @@ impl Plugin for UnityLog {
             )
             .switch(
                 "no-collapse",
-                "Do not collapse same log statements together.",
+                "Show every raw line (count = 1) instead of collapsing identical entries with a count.",
                 Some('n'),
             )
+            .named(
+                "min-level",
+                SyntaxShape::String,
+                "Drop entries below this severity (log, warning, error).",
+                Some('l'),
+            )
+            .named(
+                "match",
+                SyntaxShape::String,
+                "Only keep entries whose message matches this regex.",
+                Some('m'),
+            )
+            .named(
+                "ignore",
+                SyntaxShape::String,
+                "Drop entries whose message matches this regex.",
+                None,
+            )
+            .switch(
+                "frames",
+                "Parse the callstack into a structured 'frames' table.",
+                Some('f'),
+            )
+            .switch(
+                "color",
+                "Colorize the 'type' and 'message' cells by severity with ANSI escapes.",
+                Some('C'),
+            )
+            .switch(
+                "snippet",
+                "Attach the referenced source line (plus context) of the topmost frame with a file reference as a 'source' column.",
+                Some('s'),
+            )
+            .named(
+                "project-root",
+                SyntaxShape::String,
+                "Path the '(at Assets/...)' frame references are resolved against for --snippet.",
+                None,
+            )
             .filter())
     }
 
@@ -203,12 +462,132 @@ impl Plugin for UnityLog {
             Some(n) => self.no_collapse = n.as_bool()?,
         }
 
+        if let Some(level) = call_info_args.args.get("min-level") {
+            let name = level.as_string()?;
+            self.min_level = Some(LogType::from_level(&name).ok_or_else(|| {
+                ShellError::labeled_error(
+                    "Unknown log level",
+                    "expected one of: log, warning, error",
+                    level.tag.span,
+                )
+            })?);
+        }
+
+        if let Some(pattern) = call_info_args.args.get("match") {
+            let source = pattern.as_string()?;
+            self.match_re = Some(Regex::new(&source).map_err(|e| {
+                ShellError::labeled_error("Invalid regex", e.to_string(), pattern.tag.span)
+            })?);
+        }
+
+        if let Some(pattern) = call_info_args.args.get("ignore") {
+            let source = pattern.as_string()?;
+            self.ignore_re = Some(Regex::new(&source).map_err(|e| {
+                ShellError::labeled_error("Invalid regex", e.to_string(), pattern.tag.span)
+            })?);
+        }
+
+        match call_info_args.args.get("frames") {
+            None => {}
+            Some(f) => self.frames = f.as_bool()?,
+        }
+
+        match call_info_args.args.get("color") {
+            None => {}
+            Some(c) => self.color = c.as_bool()?,
+        }
+
+        match call_info_args.args.get("snippet") {
+            None => {}
+            Some(s) => self.snippet = s.as_bool()?,
+        }
+
+        if let Some(root) = call_info_args.args.get("project-root") {
+            self.project_root = Some(root.as_string()?);
+        }
+
         Ok(vec![])
     }
 
     fn filter(&mut self, input: Value) -> Result<Vec<ReturnValue>, ShellError> {
-        let output = self.len(input);
-        Ok(output?.into_iter().map(ReturnSuccess::value).collect())
+        let chunk = match &input.value {
+            UntaggedValue::Primitive(Primitive::String(s)) => s,
+            _ => {
+                return Err(ShellError::labeled_error(
+                    "Unrecognized type in stream",
+                    "'unity' given non-string info by this",
+                    input.tag.span,
+                ))
+            }
+        };
+
+        // Normalize line endings per chunk and append to the tail buffer, then
+        // peel off every block terminated by a blank line. The final,
+        // possibly-incomplete block is carried over to the next chunk.
+        //
+        // Under `--no-collapse`, emission is progressive: Editor.log keyword
+        // blocks return as soon as they close, and once a log is recognized as
+        // a Player.log its blocks stream too (only the first few are buffered
+        // while the classification settles). The default (collapse) path still
+        // needs the full set to count repeats, so it flushes in `end_filter`.
+        let normalized = chunk.replace("\r\n", "\n").replace('\r', "\n");
+        self.tail.push_str(&normalized);
+        self.tag = Some(input.tag.clone());
+
+        let mut out = Vec::new();
+        while let Some(index) = self.tail.find(EMPTY_NEWLINE) {
+            let block: String = self.tail.drain(..index).collect();
+            self.tail.drain(..EMPTY_NEWLINE.len());
+            if let Some(line) = self.parse_block(&block) {
+                self.consume(line, &mut out);
+            }
+        }
+
+        Ok(out)
+    }
+
+    fn end_filter(&mut self) -> Result<Vec<ReturnValue>, ShellError> {
+        let mut out = Vec::new();
+
+        // Flush the trailing block that had no terminating blank line.
+        let tail = std::mem::take(&mut self.tail);
+        if let Some(line) = self.parse_block(&tail) {
+            self.consume(line, &mut out);
+        }
+
+        // A short, keyword-less log never reached either threshold: treat it as
+        // a Player.log and emit the parked blocks as `Unknown`. (Editor mode
+        // clears `pending`; committed Player mode already drained it.)
+        if !self.editor_mode && !self.player_mode {
+            for line in std::mem::take(&mut self.pending) {
+                self.route(line, &mut out);
+            }
+        }
+
+        // Collapse pass (the default): sort the held-back lines and group
+        // identical entries, recording how many times each one repeated.
+        if !self.no_collapse {
+            let tag = self.tag.clone().unwrap_or_else(Tag::unknown);
+            self.lines.sort_by(|a, b| {
+                a.message
+                    .cmp(&b.message)
+                    .then_with(|| a.log_type.severity().cmp(&b.log_type.severity()))
+            });
+
+            let mut groups: Vec<(LogLine, usize)> = Vec::new();
+            for line in std::mem::take(&mut self.lines) {
+                match groups.last_mut() {
+                    Some(last) if last.0.same(&line) => last.1 += 1,
+                    _ => groups.push((line, 1)),
+                }
+            }
+
+            for (line, count) in &groups {
+                out.push(ReturnSuccess::value(self.build_row(line, &tag, *count)));
+            }
+        }
+
+        Ok(out)
     }
 }
 